@@ -15,6 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::collections::HashMap;
 use std::fmt;
 use std::mem::size_of;
 use std::slice::from_raw_parts;
@@ -22,6 +23,22 @@ use std::slice::from_raw_parts;
 use error::{ArrowError, Result};
 use serde_json::Value;
 
+/// Date resolution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateUnit {
+    Day,
+    Millisecond,
+}
+
+/// Time/timestamp resolution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeUnit {
+    Second,
+    Millisecond,
+    Microsecond,
+    Nanosecond,
+}
+
 /// Arrow data type
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DataType {
@@ -38,16 +55,79 @@ pub enum DataType {
     Float32,
     Float64,
     Utf8,
+    Json,
+    Date(DateUnit),
+    Time(TimeUnit, i32),
+    Timestamp(TimeUnit, Option<String>),
+    Binary,
+    FixedSizeBinary(i32),
+    Decimal(i32, i32),
     List(Box<DataType>),
+    FixedSizeList(Box<DataType>, i32),
     Struct(Vec<Field>),
 }
 
 /// Arrow struct/schema field
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct Field {
     name: String,
     data_type: DataType,
     nullable: bool,
+    metadata: HashMap<String, String>,
+}
+
+impl fmt::Debug for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Mirror the derived rendering, but omit empty metadata so that the
+        // overwhelmingly common metadata-free field keeps its original shape.
+        let mut s = f.debug_struct("Field");
+        s.field("name", &self.name)
+            .field("data_type", &self.data_type)
+            .field("nullable", &self.nullable);
+        if !self.metadata.is_empty() {
+            s.field("metadata", &self.metadata);
+        }
+        s.finish()
+    }
+}
+
+/// Serialize a metadata map as an Arrow `[{"key":..,"value":..}]` array with a
+/// deterministic (key-sorted) ordering.
+fn metadata_to_json(metadata: &HashMap<String, String>) -> Value {
+    let mut entries = metadata.iter().collect::<Vec<(&String, &String)>>();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    Value::Array(
+        entries
+            .iter()
+            .map(|&(k, v)| json!({"key": k, "value": v}))
+            .collect::<Vec<Value>>(),
+    )
+}
+
+/// Parse a metadata map from an Arrow `[{"key":..,"value":..}]` array.
+fn metadata_from_json(value: Option<&Value>) -> Result<HashMap<String, String>> {
+    let mut metadata = HashMap::new();
+    match value {
+        None => Ok(metadata),
+        Some(&Value::Array(ref entries)) => {
+            for entry in entries {
+                match (entry.get("key"), entry.get("value")) {
+                    (Some(&Value::String(ref k)), Some(&Value::String(ref v))) => {
+                        metadata.insert(k.to_string(), v.to_string());
+                    }
+                    _ => {
+                        return Err(ArrowError::ParseError(
+                            "metadata entry missing 'key' or 'value'".to_string(),
+                        ))
+                    }
+                }
+            }
+            Ok(metadata)
+        }
+        _ => Err(ArrowError::ParseError(
+            "metadata must be an array".to_string(),
+        )),
+    }
 }
 
 /// Primitive type (ints, floats, strings)
@@ -90,6 +170,61 @@ where
     }
 }
 
+/// The Arrow JSON spelling of a `TimeUnit`
+fn time_unit_name(unit: &TimeUnit) -> &'static str {
+    match *unit {
+        TimeUnit::Second => "SECOND",
+        TimeUnit::Millisecond => "MILLISECOND",
+        TimeUnit::Microsecond => "MICROSECOND",
+        TimeUnit::Nanosecond => "NANOSECOND",
+    }
+}
+
+/// Read the element `DataType` from a list node's `children` array, which by
+/// Arrow convention holds exactly one child `Field` (named `"item"`).
+fn list_child(map: &::serde_json::Map<String, Value>) -> Result<DataType> {
+    match map.get("children") {
+        Some(&Value::Array(ref children)) if children.len() == 1 => {
+            Ok(Field::from(&children[0])?.data_type().clone())
+        }
+        _ => Err(ArrowError::ParseError(
+            "list must have exactly one child field".to_string(),
+        )),
+    }
+}
+
+/// Parse a `TimeUnit` from the `"unit"` attribute of a JSON type node
+fn parse_time_unit(unit: Option<&Value>) -> Result<TimeUnit> {
+    match unit {
+        Some(u) if u == "SECOND" => Ok(TimeUnit::Second),
+        Some(u) if u == "MILLISECOND" => Ok(TimeUnit::Millisecond),
+        Some(u) if u == "MICROSECOND" => Ok(TimeUnit::Microsecond),
+        Some(u) if u == "NANOSECOND" => Ok(TimeUnit::Nanosecond),
+        _ => Err(ArrowError::ParseError(
+            "time unit missing or invalid".to_string(),
+        )),
+    }
+}
+
+/// Validate that a value destined for a `DataType::Json` column is a
+/// structured object or array, then encode it as its length-prefixed UTF-8
+/// serialization (a little-endian `i32` byte count followed by the bytes) so it
+/// can live alongside the other fixed-width buffers.
+pub fn encode_json_value(value: &Value) -> Result<Vec<u8>> {
+    match *value {
+        Value::Object(_) | Value::Array(_) => {
+            let bytes = value.to_string().into_bytes();
+            let mut out = Vec::with_capacity(4 + bytes.len());
+            out.extend_from_slice((bytes.len() as i32).to_byte_slice());
+            out.extend_from_slice(&bytes);
+            Ok(out)
+        }
+        _ => Err(ArrowError::ParseError(
+            "json column values must be an object or array".to_string(),
+        )),
+    }
+}
+
 impl DataType {
     /// Parse a data type from a JSON representation
     fn from(json: &Value) -> Result<DataType> {
@@ -97,6 +232,84 @@ impl DataType {
             Value::Object(ref map) => match map.get("name") {
                 Some(s) if s == "bool" => Ok(DataType::Boolean),
                 Some(s) if s == "utf8" => Ok(DataType::Utf8),
+                Some(s) if s == "json" => Ok(DataType::Json),
+                Some(s) if s == "list" => {
+                    let child = list_child(map)?;
+                    Ok(DataType::List(Box::new(child)))
+                }
+                Some(s) if s == "fixedsizelist" => {
+                    let child = list_child(map)?;
+                    match map.get("listSize") {
+                        Some(&Value::Number(ref n)) => match n.as_i64() {
+                            Some(size) => {
+                                Ok(DataType::FixedSizeList(Box::new(child), size as i32))
+                            }
+                            None => Err(ArrowError::ParseError(
+                                "fixedsizelist listSize missing or invalid".to_string(),
+                            )),
+                        },
+                        _ => Err(ArrowError::ParseError(
+                            "fixedsizelist listSize missing or invalid".to_string(),
+                        )),
+                    }
+                }
+                Some(s) if s == "binary" => Ok(DataType::Binary),
+                Some(s) if s == "fixedsizebinary" => match map.get("byteLength") {
+                    Some(&Value::Number(ref n)) => match n.as_i64() {
+                        Some(len) => Ok(DataType::FixedSizeBinary(len as i32)),
+                        None => Err(ArrowError::ParseError(
+                            "fixedsizebinary byteLength missing or invalid".to_string(),
+                        )),
+                    },
+                    _ => Err(ArrowError::ParseError(
+                        "fixedsizebinary byteLength missing or invalid".to_string(),
+                    )),
+                },
+                Some(s) if s == "decimal" => {
+                    let precision = match map.get("precision") {
+                        Some(&Value::Number(ref n)) => n.as_i64(),
+                        _ => None,
+                    };
+                    let scale = match map.get("scale") {
+                        Some(&Value::Number(ref n)) => n.as_i64(),
+                        _ => None,
+                    };
+                    match (precision, scale) {
+                        (Some(p), Some(s)) => Ok(DataType::Decimal(p as i32, s as i32)),
+                        _ => Err(ArrowError::ParseError(
+                            "decimal precision or scale missing or invalid".to_string(),
+                        )),
+                    }
+                }
+                Some(s) if s == "date" => match map.get("unit") {
+                    Some(u) if u == "DAY" => Ok(DataType::Date(DateUnit::Day)),
+                    Some(u) if u == "MILLISECOND" => Ok(DataType::Date(DateUnit::Millisecond)),
+                    _ => Err(ArrowError::ParseError(
+                        "date unit missing or invalid".to_string(),
+                    )),
+                },
+                Some(s) if s == "time" => {
+                    let unit = parse_time_unit(map.get("unit"))?;
+                    match map.get("bitWidth") {
+                        Some(&Value::Number(ref n)) => match n.as_i64() {
+                            Some(bits) => Ok(DataType::Time(unit, bits as i32)),
+                            None => Err(ArrowError::ParseError(
+                                "time bitWidth missing or invalid".to_string(),
+                            )),
+                        },
+                        _ => Err(ArrowError::ParseError(
+                            "time bitWidth missing or invalid".to_string(),
+                        )),
+                    }
+                }
+                Some(s) if s == "timestamp" => {
+                    let unit = parse_time_unit(map.get("unit"))?;
+                    let timezone = match map.get("timezone") {
+                        Some(&Value::String(ref tz)) => Some(tz.to_string()),
+                        _ => None,
+                    };
+                    Ok(DataType::Timestamp(unit, timezone))
+                }
                 Some(s) if s == "floatingpoint" => match map.get("precision") {
                     Some(p) if p == "HALF" => Ok(DataType::Float16),
                     Some(p) if p == "SINGLE" => Ok(DataType::Float32),
@@ -111,7 +324,7 @@ impl DataType {
                             Some(8) => Ok(DataType::Int8),
                             Some(16) => Ok(DataType::Int16),
                             Some(32) => Ok(DataType::Int32),
-                            Some(64) => Ok(DataType::Int32),
+                            Some(64) => Ok(DataType::Int64),
                             _ => Err(ArrowError::ParseError(
                                 "int bitWidth missing or invalid".to_string(),
                             )),
@@ -175,14 +388,46 @@ impl DataType {
             DataType::Float32 => json!({"name": "floatingpoint", "precision": "SINGLE"}),
             DataType::Float64 => json!({"name": "floatingpoint", "precision": "DOUBLE"}),
             DataType::Utf8 => json!({"name": "utf8"}),
+            DataType::Json => json!({"name": "json"}),
+            DataType::Binary => json!({"name": "binary"}),
+            DataType::FixedSizeBinary(len) => {
+                json!({"name": "fixedsizebinary", "byteLength": len})
+            }
+            DataType::Decimal(precision, scale) => {
+                json!({"name": "decimal", "precision": precision, "scale": scale})
+            }
+            DataType::Date(ref unit) => {
+                let unit = match *unit {
+                    DateUnit::Day => "DAY",
+                    DateUnit::Millisecond => "MILLISECOND",
+                };
+                json!({"name": "date", "unit": unit})
+            }
+            DataType::Time(ref unit, bit_width) => {
+                json!({"name": "time", "unit": time_unit_name(unit), "bitWidth": bit_width})
+            }
+            DataType::Timestamp(ref unit, ref timezone) => match *timezone {
+                Some(ref tz) => {
+                    json!({"name": "timestamp", "unit": time_unit_name(unit), "timezone": tz})
+                }
+                None => json!({"name": "timestamp", "unit": time_unit_name(unit)}),
+            },
             DataType::Struct(ref fields) => {
                 let field_json_array =
                     Value::Array(fields.iter().map(|f| f.to_json()).collect::<Vec<Value>>());
                 json!({ "fields": field_json_array })
             }
             DataType::List(ref t) => {
-                let child_json = t.to_json();
-                json!({ "name": "list", "children": child_json })
+                let child = Field::new("item", (**t).clone(), true);
+                json!({ "name": "list", "children": [child.to_json()] })
+            }
+            DataType::FixedSizeList(ref t, size) => {
+                let child = Field::new("item", (**t).clone(), true);
+                json!({
+                    "name": "fixedsizelist",
+                    "listSize": size,
+                    "children": [child.to_json()],
+                })
             }
         }
     }
@@ -194,13 +439,24 @@ impl Field {
             name: name.to_string(),
             data_type,
             nullable,
+            metadata: HashMap::new(),
         }
     }
 
+    /// Attach custom key-value metadata, returning the updated field.
+    pub fn with_metadata(mut self, metadata: HashMap<String, String>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
     pub fn name(&self) -> &String {
         &self.name
     }
 
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
+    }
+
     pub fn data_type(&self) -> &DataType {
         &self.data_type
     }
@@ -237,10 +493,12 @@ impl Field {
                         ))
                     }
                 };
+                let metadata = metadata_from_json(map.get("metadata"))?;
                 Ok(Field {
                     name,
                     nullable,
                     data_type,
+                    metadata,
                 })
             }
             _ => Err(ArrowError::ParseError(
@@ -251,11 +509,15 @@ impl Field {
 
     /// Generate a JSON representation of the field
     pub fn to_json(&self) -> Value {
-        json!({
+        let mut value = json!({
             "name": self.name,
             "nullable": self.nullable,
             "type": self.data_type.to_json(),
-        })
+        });
+        if !self.metadata.is_empty() {
+            value["metadata"] = metadata_to_json(&self.metadata);
+        }
+        value
     }
 
     pub fn to_string(&self) -> String {
@@ -273,16 +535,33 @@ impl fmt::Display for Field {
 #[derive(Debug, Clone)]
 pub struct Schema {
     columns: Vec<Field>,
+    metadata: HashMap<String, String>,
 }
 
 impl Schema {
     /// create an empty schema
     pub fn empty() -> Self {
-        Schema { columns: vec![] }
+        Schema {
+            columns: vec![],
+            metadata: HashMap::new(),
+        }
     }
 
     pub fn new(columns: Vec<Field>) -> Self {
-        Schema { columns }
+        Schema {
+            columns,
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Attach custom key-value metadata, returning the updated schema.
+    pub fn with_metadata(mut self, metadata: HashMap<String, String>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
     }
 
     pub fn columns(&self) -> &Vec<Field> {
@@ -398,6 +677,82 @@ mod tests {
         assert_eq!(DataType::Int32, dt);
     }
 
+    #[test]
+    fn parse_int64_from_json() {
+        let json = "{\"name\": \"int\", \"isSigned\": true, \"bitWidth\": 64}";
+        let value: Value = serde_json::from_str(json).unwrap();
+        let dt = DataType::from(&value).unwrap();
+        assert_eq!(DataType::Int64, dt);
+    }
+
+    #[test]
+    fn round_trip_logical_types() {
+        let types = vec![
+            DataType::Binary,
+            DataType::FixedSizeBinary(16),
+            DataType::Decimal(38, 10),
+            DataType::Date(DateUnit::Day),
+            DataType::Date(DateUnit::Millisecond),
+            DataType::Time(TimeUnit::Microsecond, 64),
+            DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".to_string())),
+            DataType::Timestamp(TimeUnit::Second, None),
+        ];
+        for dt in types {
+            let parsed = DataType::from(&dt.to_json()).unwrap();
+            assert_eq!(dt, parsed);
+        }
+    }
+
+    #[test]
+    fn list_of_struct_round_trip() {
+        let dt = DataType::List(Box::new(DataType::Struct(vec![
+            Field::new("street", DataType::Utf8, false),
+            Field::new("zip", DataType::UInt16, false),
+        ])));
+        assert_eq!(DataType::from(&dt.to_json()).unwrap(), dt);
+    }
+
+    #[test]
+    fn nested_list_round_trip() {
+        let dt = DataType::List(Box::new(DataType::List(Box::new(DataType::Int32))));
+        assert_eq!(DataType::from(&dt.to_json()).unwrap(), dt);
+    }
+
+    #[test]
+    fn fixed_size_list_round_trip() {
+        let dt = DataType::FixedSizeList(Box::new(DataType::Float64), 3);
+        assert_eq!(DataType::from(&dt.to_json()).unwrap(), dt);
+    }
+
+    #[test]
+    fn parse_json_type_round_trip() {
+        let dt = DataType::Json;
+        assert_eq!("{\"name\":\"json\"}", dt.to_json().to_string());
+        assert_eq!(DataType::from(&dt.to_json()).unwrap(), DataType::Json);
+    }
+
+    #[test]
+    fn encode_json_value_rejects_scalars() {
+        let value: Value = serde_json::from_str("{\"a\":1}").unwrap();
+        let encoded = encode_json_value(&value).unwrap();
+        assert_eq!(&encoded[0..4], (7i32).to_byte_slice());
+        assert_eq!(&encoded[4..], b"{\"a\":1}");
+
+        let scalar: Value = serde_json::from_str("42").unwrap();
+        assert!(encode_json_value(&scalar).is_err());
+    }
+
+    #[test]
+    fn field_metadata_round_trip() {
+        let mut metadata = HashMap::new();
+        metadata.insert("sql_type".to_string(), "VARCHAR".to_string());
+        metadata.insert("encoding".to_string(), "dictionary".to_string());
+        let f = Field::new("first_name", DataType::Utf8, false).with_metadata(metadata);
+        let parsed = Field::from(&f.to_json()).unwrap();
+        assert_eq!(f, parsed);
+        assert_eq!(parsed.metadata().get("sql_type").unwrap(), "VARCHAR");
+    }
+
     #[test]
     fn create_schema_string() {
         let _person = Schema::new(vec![
@@ -0,0 +1,675 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Newline-delimited JSON reader that builds column-oriented buffers from a
+//! `Schema`. Each line of input is a JSON object; for every field the matching
+//! value is coerced to the field's `DataType` and appended into a per-column
+//! builder, tracking a validity bit whenever the key is absent or null.
+
+use std::io::{BufRead, BufReader, Read};
+
+use datatypes::{DataType, Field, Schema};
+use error::{ArrowError, Result};
+use serde_json::Value;
+
+/// Column-oriented value buffer produced by the reader, one per leaf encoding.
+#[derive(Debug, PartialEq)]
+pub enum Buffer {
+    Boolean(Vec<bool>),
+    Int8(Vec<i8>),
+    Int16(Vec<i16>),
+    Int32(Vec<i32>),
+    Int64(Vec<i64>),
+    UInt8(Vec<u8>),
+    UInt16(Vec<u16>),
+    UInt32(Vec<u32>),
+    UInt64(Vec<u64>),
+    Float32(Vec<f32>),
+    Float64(Vec<f64>),
+    Utf8(Vec<String>),
+    /// A list column: `offsets` has `len + 1` entries delimiting each row's
+    /// slice of the flattened child builder.
+    List {
+        offsets: Vec<i32>,
+        child: Box<ColumnBuilder>,
+    },
+    /// A struct column: one child builder per sub-field.
+    Struct(Vec<ColumnBuilder>),
+}
+
+/// A builder that accumulates the values of a single column along with a
+/// validity bit per row.
+#[derive(Debug, PartialEq)]
+pub struct ColumnBuilder {
+    field: Field,
+    validity: Vec<bool>,
+    buffer: Buffer,
+}
+
+impl ColumnBuilder {
+    /// Create an empty builder for the given field.
+    pub fn new(field: &Field) -> Result<Self> {
+        let buffer = ColumnBuilder::empty_buffer(field.data_type())?;
+        Ok(ColumnBuilder {
+            field: field.clone(),
+            validity: Vec::new(),
+            buffer,
+        })
+    }
+
+    fn empty_buffer(data_type: &DataType) -> Result<Buffer> {
+        Ok(match *data_type {
+            DataType::Boolean => Buffer::Boolean(Vec::new()),
+            DataType::Int8 => Buffer::Int8(Vec::new()),
+            DataType::Int16 => Buffer::Int16(Vec::new()),
+            DataType::Int32 => Buffer::Int32(Vec::new()),
+            DataType::Int64 => Buffer::Int64(Vec::new()),
+            DataType::UInt8 => Buffer::UInt8(Vec::new()),
+            DataType::UInt16 => Buffer::UInt16(Vec::new()),
+            DataType::UInt32 => Buffer::UInt32(Vec::new()),
+            DataType::UInt64 => Buffer::UInt64(Vec::new()),
+            DataType::Float32 => Buffer::Float32(Vec::new()),
+            DataType::Float64 => Buffer::Float64(Vec::new()),
+            DataType::Utf8 => Buffer::Utf8(Vec::new()),
+            DataType::List(ref child) => {
+                let item = Field::new("item", (**child).clone(), true);
+                Buffer::List {
+                    offsets: vec![0],
+                    child: Box::new(ColumnBuilder::new(&item)?),
+                }
+            }
+            DataType::Struct(ref fields) => Buffer::Struct(
+                fields
+                    .iter()
+                    .map(ColumnBuilder::new)
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            ref other => {
+                return Err(ArrowError::ParseError(format!(
+                    "column builder does not support {:?}",
+                    other
+                )))
+            }
+        })
+    }
+
+    /// The field this builder was created for.
+    pub fn field(&self) -> &Field {
+        &self.field
+    }
+
+    /// The accumulated values.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// The per-row validity bits; `false` means the row was null or absent.
+    pub fn validity(&self) -> &[bool] {
+        &self.validity
+    }
+
+    /// Append one JSON value, coercing it to the column's `DataType`. A missing
+    /// (`None`) or `null` value appends a default and records a null bit, unless
+    /// the field is non-nullable in which case it is an error.
+    pub fn append(&mut self, value: Option<&Value>) -> Result<()> {
+        match value {
+            None | Some(&Value::Null) => {
+                if !self.field.is_nullable() {
+                    return Err(ArrowError::ParseError(format!(
+                        "null value for non-nullable field '{}'",
+                        self.field.name()
+                    )));
+                }
+                self.append_null()
+            }
+            Some(value) => {
+                self.validity.push(true);
+                self.append_value(value)
+            }
+        }
+    }
+
+    fn append_default(&mut self) -> Result<()> {
+        match self.buffer {
+            Buffer::Boolean(ref mut v) => v.push(false),
+            Buffer::Int8(ref mut v) => v.push(0),
+            Buffer::Int16(ref mut v) => v.push(0),
+            Buffer::Int32(ref mut v) => v.push(0),
+            Buffer::Int64(ref mut v) => v.push(0),
+            Buffer::UInt8(ref mut v) => v.push(0),
+            Buffer::UInt16(ref mut v) => v.push(0),
+            Buffer::UInt32(ref mut v) => v.push(0),
+            Buffer::UInt64(ref mut v) => v.push(0),
+            Buffer::Float32(ref mut v) => v.push(0.0),
+            Buffer::Float64(ref mut v) => v.push(0.0),
+            Buffer::Utf8(ref mut v) => v.push(String::new()),
+            Buffer::List {
+                ref mut offsets, ..
+            } => {
+                let last = *offsets.last().unwrap();
+                offsets.push(last);
+            }
+            Buffer::Struct(ref mut children) => {
+                // A null parent nulls the whole sub-tree regardless of child
+                // nullability, so bypass each child's own null check.
+                for child in children.iter_mut() {
+                    child.append_null()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Push a null bit and a default value without consulting `is_nullable`;
+    /// used when a null is forced by a null parent rather than the row itself.
+    fn append_null(&mut self) -> Result<()> {
+        self.validity.push(false);
+        self.append_default()
+    }
+
+    fn append_value(&mut self, value: &Value) -> Result<()> {
+        match self.buffer {
+            Buffer::Boolean(ref mut v) => v.push(as_bool(value, &self.field)?),
+            Buffer::Int8(ref mut v) => v.push(as_i64(value, &self.field)? as i8),
+            Buffer::Int16(ref mut v) => v.push(as_i64(value, &self.field)? as i16),
+            Buffer::Int32(ref mut v) => v.push(as_i64(value, &self.field)? as i32),
+            Buffer::Int64(ref mut v) => v.push(as_i64(value, &self.field)?),
+            Buffer::UInt8(ref mut v) => v.push(as_u64(value, &self.field)? as u8),
+            Buffer::UInt16(ref mut v) => v.push(as_u64(value, &self.field)? as u16),
+            Buffer::UInt32(ref mut v) => v.push(as_u64(value, &self.field)? as u32),
+            Buffer::UInt64(ref mut v) => v.push(as_u64(value, &self.field)?),
+            Buffer::Float32(ref mut v) => v.push(as_f64(value, &self.field)? as f32),
+            Buffer::Float64(ref mut v) => v.push(as_f64(value, &self.field)?),
+            Buffer::Utf8(ref mut v) => v.push(as_str(value, &self.field)?),
+            Buffer::List {
+                ref mut offsets,
+                ref mut child,
+            } => match *value {
+                Value::Array(ref items) => {
+                    for item in items {
+                        child.append(Some(item))?;
+                    }
+                    let last = *offsets.last().unwrap();
+                    offsets.push(last + items.len() as i32);
+                }
+                _ => {
+                    return Err(ArrowError::ParseError(format!(
+                        "expected JSON array for list field '{}'",
+                        self.field.name()
+                    )))
+                }
+            },
+            Buffer::Struct(ref mut children) => match *value {
+                Value::Object(ref map) => {
+                    for child in children.iter_mut() {
+                        let key = child.field.name().clone();
+                        child.append(map.get(&key))?;
+                    }
+                }
+                _ => {
+                    return Err(ArrowError::ParseError(format!(
+                        "expected JSON object for struct field '{}'",
+                        self.field.name()
+                    )))
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+fn as_bool(value: &Value, field: &Field) -> Result<bool> {
+    value
+        .as_bool()
+        .ok_or_else(|| type_error(value, field, "boolean"))
+}
+
+fn as_i64(value: &Value, field: &Field) -> Result<i64> {
+    value
+        .as_i64()
+        .ok_or_else(|| type_error(value, field, "signed integer"))
+}
+
+fn as_u64(value: &Value, field: &Field) -> Result<u64> {
+    value
+        .as_u64()
+        .ok_or_else(|| type_error(value, field, "unsigned integer"))
+}
+
+fn as_f64(value: &Value, field: &Field) -> Result<f64> {
+    value
+        .as_f64()
+        .ok_or_else(|| type_error(value, field, "float"))
+}
+
+fn as_str(value: &Value, field: &Field) -> Result<String> {
+    value
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| type_error(value, field, "string"))
+}
+
+fn type_error(value: &Value, field: &Field, expected: &str) -> ArrowError {
+    ArrowError::ParseError(format!(
+        "expected {} for field '{}' but found {}",
+        expected,
+        field.name(),
+        value
+    ))
+}
+
+/// Reads newline-delimited JSON records into columnar batches of a fixed size.
+pub struct Reader<R: Read> {
+    schema: Schema,
+    reader: BufReader<R>,
+    batch_size: usize,
+}
+
+impl<R: Read> Reader<R> {
+    /// Create a reader that yields batches of at most `batch_size` rows shaped
+    /// by `schema`.
+    pub fn new(schema: Schema, reader: R, batch_size: usize) -> Result<Self> {
+        // Validate up front that every column has a supported builder so that
+        // creating the builders in `next_batch` cannot fail; malformed or
+        // mistyped records still make `next_batch` return an error.
+        for field in schema.columns() {
+            ColumnBuilder::new(field)?;
+        }
+        Ok(Reader {
+            schema,
+            reader: BufReader::new(reader),
+            batch_size,
+        })
+    }
+
+    /// The schema each batch conforms to.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Read up to `batch_size` records, returning one builder per column.
+    /// Returns `None` once the input is exhausted without reading any rows.
+    pub fn next_batch(&mut self) -> Result<Option<Vec<ColumnBuilder>>> {
+        let mut builders: Vec<ColumnBuilder> = self
+            .schema
+            .columns()
+            .iter()
+            .map(ColumnBuilder::new)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut rows = 0;
+        let mut line = String::new();
+        while rows < self.batch_size {
+            line.clear();
+            let read = self
+                .reader
+                .read_line(&mut line)
+                .map_err(|e| ArrowError::ParseError(e.to_string()))?;
+            if read == 0 {
+                break;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value: Value = ::serde_json::from_str(line.trim())
+                .map_err(|e| ArrowError::ParseError(e.to_string()))?;
+            let map = match value {
+                Value::Object(map) => map,
+                _ => {
+                    return Err(ArrowError::ParseError(
+                        "expected a JSON object per line".to_string(),
+                    ))
+                }
+            };
+            for (i, builder) in builders.iter_mut().enumerate() {
+                let name = self.schema.column(i).name().clone();
+                builder.append(map.get(&name))?;
+            }
+            rows += 1;
+        }
+
+        if rows == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(builders))
+        }
+    }
+}
+
+/// Scan up to `max_records` records and synthesize a `Schema`, widening numeric
+/// types to the smallest type that fits and promoting to `Utf8` on conflict.
+pub fn infer_schema<R: Read>(reader: R, max_records: usize) -> Result<Schema> {
+    let mut reader = BufReader::new(reader);
+    // Per candidate: name, inferred type (`None` until a non-null value is
+    // seen), whether an explicit null was seen, and the number of scanned
+    // records the key was present in.
+    let mut candidates: Vec<(String, Option<DataType>, bool, usize)> = Vec::new();
+    let mut line = String::new();
+    let mut scanned = 0;
+
+    while scanned < max_records {
+        line.clear();
+        let read = reader
+            .read_line(&mut line)
+            .map_err(|e| ArrowError::ParseError(e.to_string()))?;
+        if read == 0 {
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = ::serde_json::from_str(line.trim())
+            .map_err(|e| ArrowError::ParseError(e.to_string()))?;
+        let map = match value {
+            Value::Object(map) => map,
+            _ => {
+                return Err(ArrowError::ParseError(
+                    "expected a JSON object per line".to_string(),
+                ))
+            }
+        };
+        for (key, value) in map.iter() {
+            let observed = infer_value_type(value);
+            match candidates.iter_mut().find(|&&mut (ref k, _, _, _)| k == key) {
+                Some(&mut (_, ref mut dt, ref mut nullable, ref mut present)) => {
+                    *present += 1;
+                    match observed {
+                        Some(observed) => {
+                            *dt = Some(match *dt {
+                                Some(ref dt) => widen(dt, &observed),
+                                None => observed,
+                            });
+                        }
+                        None => *nullable = true,
+                    }
+                }
+                None => candidates.push((
+                    key.clone(),
+                    observed,
+                    observed_is_null(value),
+                    1,
+                )),
+            }
+        }
+        scanned += 1;
+    }
+
+    // A field is nullable if an explicit null was seen or it was absent from at
+    // least one scanned record.
+    let fields = candidates
+        .into_iter()
+        .map(|(name, dt, nullable, present)| {
+            // A column observed only as null defaults to `Utf8`.
+            let dt = dt.unwrap_or(DataType::Utf8);
+            Field::new(&name, dt, nullable || present < scanned)
+        })
+        .collect();
+    Ok(Schema::new(fields))
+}
+
+fn observed_is_null(value: &Value) -> bool {
+    value.is_null()
+}
+
+/// The narrowest `DataType` that represents a single observed JSON value, or
+/// `None` for `null` (which only influences nullability).
+fn infer_value_type(value: &Value) -> Option<DataType> {
+    match *value {
+        Value::Null => None,
+        Value::Bool(_) => Some(DataType::Boolean),
+        Value::Number(ref n) => {
+            if let Some(u) = n.as_u64() {
+                Some(smallest_uint(u))
+            } else if let Some(i) = n.as_i64() {
+                Some(smallest_int(i))
+            } else {
+                // A real: prefer `Float32` when the value survives the round-trip.
+                let f = n.as_f64().unwrap();
+                if f as f32 as f64 == f {
+                    Some(DataType::Float32)
+                } else {
+                    Some(DataType::Float64)
+                }
+            }
+        }
+        Value::String(_) => Some(DataType::Utf8),
+        Value::Array(ref items) => {
+            let child = items
+                .iter()
+                .filter_map(infer_value_type)
+                .fold(None, |acc: Option<DataType>, dt| match acc {
+                    Some(ref acc) => Some(widen(acc, &dt)),
+                    None => Some(dt),
+                })
+                .unwrap_or(DataType::Utf8);
+            Some(DataType::List(Box::new(child)))
+        }
+        Value::Object(ref map) => {
+            let fields = map
+                .iter()
+                .map(|(k, v)| {
+                    Field::new(k, infer_value_type(v).unwrap_or(DataType::Utf8), v.is_null())
+                })
+                .collect();
+            Some(DataType::Struct(fields))
+        }
+    }
+}
+
+/// The smallest unsigned `DataType` that holds `value`.
+fn smallest_uint(value: u64) -> DataType {
+    if value <= u64::from(::std::u8::MAX) {
+        DataType::UInt8
+    } else if value <= u64::from(::std::u16::MAX) {
+        DataType::UInt16
+    } else if value <= u64::from(::std::u32::MAX) {
+        DataType::UInt32
+    } else {
+        DataType::UInt64
+    }
+}
+
+/// The smallest signed `DataType` that holds `value`.
+fn smallest_int(value: i64) -> DataType {
+    if value >= i64::from(::std::i8::MIN) && value <= i64::from(::std::i8::MAX) {
+        DataType::Int8
+    } else if value >= i64::from(::std::i16::MIN) && value <= i64::from(::std::i16::MAX) {
+        DataType::Int16
+    } else if value >= i64::from(::std::i32::MIN) && value <= i64::from(::std::i32::MAX) {
+        DataType::Int32
+    } else {
+        DataType::Int64
+    }
+}
+
+/// Decompose a numeric `DataType` into `(is_float, is_signed, bytes)`.
+fn numeric_info(dt: &DataType) -> Option<(bool, bool, u8)> {
+    use DataType::*;
+    match *dt {
+        UInt8 => Some((false, false, 1)),
+        UInt16 => Some((false, false, 2)),
+        UInt32 => Some((false, false, 4)),
+        UInt64 => Some((false, false, 8)),
+        Int8 => Some((false, true, 1)),
+        Int16 => Some((false, true, 2)),
+        Int32 => Some((false, true, 4)),
+        Int64 => Some((false, true, 8)),
+        Float32 => Some((true, true, 4)),
+        Float64 => Some((true, true, 8)),
+        _ => None,
+    }
+}
+
+/// Widen two numeric types to the smallest type holding both, falling back to
+/// `Float64` when an unsigned 64-bit range must mix with a signed one.
+fn widen_numeric(a: (bool, bool, u8), b: (bool, bool, u8)) -> DataType {
+    let (af, asg, ab) = a;
+    let (bf, bsg, bb) = b;
+    if af || bf {
+        // Any real operand yields a float; only two `Float32`s stay narrow.
+        if ab <= 4 && bb <= 4 && af && bf {
+            return DataType::Float32;
+        }
+        return DataType::Float64;
+    }
+    match (asg, bsg) {
+        (false, false) => smallest_uint(match ab.max(bb) {
+            1 => u64::from(::std::u8::MAX),
+            2 => u64::from(::std::u16::MAX),
+            4 => u64::from(::std::u32::MAX),
+            _ => ::std::u64::MAX,
+        }),
+        (true, true) => smallest_int(match ab.max(bb) {
+            1 => i64::from(::std::i8::MIN),
+            2 => i64::from(::std::i16::MIN),
+            4 => i64::from(::std::i32::MIN),
+            _ => ::std::i64::MIN,
+        }),
+        _ => {
+            // Mixed signedness: pick the smallest signed width strictly wider
+            // than the unsigned operand, promoting to `Float64` past i64.
+            let (ubytes, sbytes) = if asg { (bb, ab) } else { (ab, bb) };
+            let need = sbytes.max(match ubytes {
+                1 => 2,
+                2 => 4,
+                4 => 8,
+                _ => 16,
+            });
+            match need {
+                2 => DataType::Int16,
+                4 => DataType::Int32,
+                8 => DataType::Int64,
+                _ => DataType::Float64,
+            }
+        }
+    }
+}
+
+/// Combine two inferred types into one that can hold both, promoting to `Utf8`
+/// when they cannot be reconciled numerically.
+fn widen(a: &DataType, b: &DataType) -> DataType {
+    use DataType::*;
+    match (a, b) {
+        (x, y) if x == y => x.clone(),
+        (List(x), List(y)) => List(Box::new(widen(x, y))),
+        _ => match (numeric_info(a), numeric_info(b)) {
+            (Some(ai), Some(bi)) => widen_numeric(ai, bi),
+            _ => Utf8,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datatypes::{DataType, Field, Schema};
+
+    #[test]
+    fn read_scalar_batch() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        let input = "{\"id\":1,\"name\":\"a\"}\n{\"id\":2}\n";
+        let mut reader = Reader::new(schema, input.as_bytes(), 1024).unwrap();
+        let batch = reader.next_batch().unwrap().unwrap();
+        assert_eq!(batch[0].buffer(), &Buffer::Int64(vec![1, 2]));
+        assert_eq!(
+            batch[1].buffer(),
+            &Buffer::Utf8(vec!["a".to_string(), String::new()])
+        );
+        assert_eq!(batch[1].validity(), &[true, false]);
+        assert!(reader.next_batch().unwrap().is_none());
+    }
+
+    #[test]
+    fn read_list_and_struct() {
+        let schema = Schema::new(vec![
+            Field::new("vals", DataType::List(Box::new(DataType::Int32)), true),
+            Field::new(
+                "addr",
+                DataType::Struct(vec![Field::new("zip", DataType::UInt16, true)]),
+                true,
+            ),
+        ]);
+        let input = "{\"vals\":[1,2,3],\"addr\":{\"zip\":94107}}\n";
+        let mut reader = Reader::new(schema, input.as_bytes(), 8).unwrap();
+        let batch = reader.next_batch().unwrap().unwrap();
+        match batch[0].buffer() {
+            &Buffer::List { ref offsets, ref child } => {
+                assert_eq!(offsets, &vec![0, 3]);
+                assert_eq!(child.buffer(), &Buffer::Int32(vec![1, 2, 3]));
+            }
+            other => panic!("unexpected buffer {:?}", other),
+        }
+    }
+
+    #[test]
+    fn infer_widens_numeric() {
+        let input = "{\"a\":1,\"b\":1.5}\n{\"a\":2,\"b\":3}\n";
+        let schema = infer_schema(input.as_bytes(), 100).unwrap();
+        let (_, a) = schema.column_with_name("a").unwrap();
+        let (_, b) = schema.column_with_name("b").unwrap();
+        assert_eq!(a.data_type(), &DataType::UInt8);
+        assert_eq!(b.data_type(), &DataType::Float64);
+    }
+
+    #[test]
+    fn infer_marks_absent_keys_nullable() {
+        let input = "{\"a\":1}\n{\"b\":2}\n";
+        let schema = infer_schema(input.as_bytes(), 100).unwrap();
+        let (_, a) = schema.column_with_name("a").unwrap();
+        let (_, b) = schema.column_with_name("b").unwrap();
+        assert!(a.is_nullable());
+        assert!(b.is_nullable());
+    }
+
+    #[test]
+    fn infer_leading_null_does_not_poison_type() {
+        let input = "{\"x\":null}\n{\"x\":5}\n";
+        let schema = infer_schema(input.as_bytes(), 100).unwrap();
+        let (_, x) = schema.column_with_name("x").unwrap();
+        assert_eq!(x.data_type(), &DataType::UInt8);
+        assert!(x.is_nullable());
+    }
+
+    #[test]
+    fn null_struct_with_non_nullable_children() {
+        let schema = Schema::new(vec![Field::new(
+            "addr",
+            DataType::Struct(vec![Field::new("zip", DataType::UInt16, false)]),
+            true,
+        )]);
+        let input = "{\"addr\":{\"zip\":94107}}\n{}\n";
+        let mut reader = Reader::new(schema, input.as_bytes(), 8).unwrap();
+        let batch = reader.next_batch().unwrap().unwrap();
+        assert_eq!(batch[0].validity(), &[true, false]);
+        match batch[0].buffer() {
+            &Buffer::Struct(ref children) => {
+                assert_eq!(children[0].buffer(), &Buffer::UInt16(vec![94107, 0]));
+                assert_eq!(children[0].validity(), &[true, false]);
+            }
+            other => panic!("unexpected buffer {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unsupported_column_type_errors() {
+        let schema = Schema::new(vec![Field::new("d", DataType::Binary, true)]);
+        assert!(Reader::new(schema, "".as_bytes(), 8).is_err());
+    }
+}